@@ -1,22 +1,168 @@
+use std::cmp::{Ordering, Reverse};
 use std::collections::BinaryHeap;
-use std::sync::mpsc::{self, Receiver, SendError, Sender, TryRecvError};
-use std::thread;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{self, Receiver, SendError, Sender, TryRecvError};
+use futures::Stream;
 
 use crate::walk::{DirEntry, DirEntryContents};
 
+/// How often (in pushes) to emit a [`ScanProgress`] update even when
+/// [`PROGRESS_EMIT_INTERVAL`] has not yet elapsed since the last one.
+const PROGRESS_EMIT_EVERY_PUSHES: u64 = 256;
+
+/// Time after which the next push emits a progress update, even if fewer
+/// than [`PROGRESS_EMIT_EVERY_PUSHES`] pushes happened since the last one.
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Running totals for an in-progress walk, sent on an optional
+/// `progress_sender` so callers can render a live counter without
+/// re-counting entries themselves.
+#[derive(Clone, Debug, Default)]
+pub struct ScanProgress {
+  pub entries_seen: u64,
+  pub dirs_entered: u64,
+  /// Total bytes across entries with metadata enabled; zero otherwise.
+  pub bytes_seen: u64,
+  pub deepest_index_path: Vec<usize>,
+}
+
+struct ProgressState {
+  sender: Sender<ScanProgress>,
+  entries_seen: AtomicU64,
+  dirs_entered: AtomicU64,
+  bytes_seen: AtomicU64,
+  pushes_since_emit: AtomicU64,
+  deepest_index_path: Mutex<Vec<usize>>,
+  last_emit: Mutex<Instant>,
+}
+
+impl ProgressState {
+  fn new(sender: Sender<ScanProgress>) -> ProgressState {
+    ProgressState {
+      sender,
+      entries_seen: AtomicU64::new(0),
+      dirs_entered: AtomicU64::new(0),
+      bytes_seen: AtomicU64::new(0),
+      pushes_since_emit: AtomicU64::new(0),
+      deepest_index_path: Mutex::new(Vec::new()),
+      last_emit: Mutex::new(Instant::now()),
+    }
+  }
+
+  fn record(&self, dent: &DirEntryContents) {
+    self.entries_seen.fetch_add(1, AtomicOrdering::Relaxed);
+    if dent.remaining_folders_with_contents > 0 {
+      self.dirs_entered.fetch_add(1, AtomicOrdering::Relaxed);
+    }
+    // `len()` is 0 for entries walked without metadata enabled, so this is a
+    // no-op unless the caller opted in, matching `ScanProgress::bytes_seen`'s
+    // doc comment.
+    self.bytes_seen.fetch_add(dent.dir_entry().len(), AtomicOrdering::Relaxed);
+
+    {
+      let mut deepest = self.deepest_index_path.lock().unwrap();
+      if dent.index_path.len() >= deepest.len() {
+        *deepest = dent.index_path.clone();
+      }
+    }
+
+    let pushes = self.pushes_since_emit.fetch_add(1, AtomicOrdering::Relaxed) + 1;
+    let due = {
+      let mut last_emit = self.last_emit.lock().unwrap();
+      if pushes >= PROGRESS_EMIT_EVERY_PUSHES || last_emit.elapsed() >= PROGRESS_EMIT_INTERVAL {
+        *last_emit = Instant::now();
+        true
+      } else {
+        false
+      }
+    };
+
+    if due {
+      self.pushes_since_emit.store(0, AtomicOrdering::Relaxed);
+      let snapshot = ScanProgress {
+        entries_seen: self.entries_seen.load(AtomicOrdering::Relaxed),
+        dirs_entered: self.dirs_entered.load(AtomicOrdering::Relaxed),
+        bytes_seen: self.bytes_seen.load(AtomicOrdering::Relaxed),
+        deepest_index_path: self.deepest_index_path.lock().unwrap().clone(),
+      };
+      // A disconnected progress receiver just means nobody is listening
+      // anymore; that shouldn't interrupt the walk.
+      let _ = self.sender.send(snapshot);
+    }
+  }
+}
+
 #[derive(Clone)]
 pub struct ResultsQueue {
   sender: Sender<DirEntryContents>,
+  // `Arc<AtomicBool>` rather than a borrowed `Receiver<()>`: every clone of
+  // `ResultsQueue` (one per worker thread) and the consuming iterator all
+  // need to observe the same stop signal. A one-shot channel message is
+  // consumed by whichever clone calls `recv`/`try_recv` first, so every
+  // other holder keeps seeing `Empty` and never learns the walk was
+  // stopped. A shared flag is safe to poll from any number of threads.
+  stop_flag: Option<Arc<AtomicBool>>,
+  progress: Option<Arc<ProgressState>>,
+  waker: Option<Arc<Mutex<Option<Waker>>>>,
 }
 
 pub struct ResultsQueueIterator {
   receiver: Receiver<DirEntryContents>,
+  stop_flag: Option<Arc<AtomicBool>>,
 }
 
 pub struct SortedResultsQueueIterator {
   receiver: Receiver<DirEntryContents>,
   receive_buffer: BinaryHeap<DirEntryContents>,
   next_matcher: SortedResultsQueueNextMatcher,
+  stop_flag: Option<Arc<AtomicBool>>,
+}
+
+/// Async adapter over [`ResultsQueue`] for consumers built on `futures`
+/// (tokio, smol, ...) that want to `.await` entries instead of spawning a
+/// dedicated blocking thread per walk.
+pub struct ResultsQueueStream {
+  receiver: Receiver<DirEntryContents>,
+  stop_flag: Option<Arc<AtomicBool>>,
+  waker: Arc<Mutex<Option<Waker>>>,
+}
+
+/// Async, order-preserving counterpart to [`SortedResultsQueueIterator`].
+/// Buffers out-of-order entries in the same `BinaryHeap` and yields
+/// `Poll::Pending` (re-arming the task's waker) until the next `index_path`
+/// in preorder is available.
+pub struct SortedResultsQueueStream {
+  receiver: Receiver<DirEntryContents>,
+  receive_buffer: BinaryHeap<DirEntryContents>,
+  next_matcher: SortedResultsQueueNextMatcher,
+  stop_flag: Option<Arc<AtomicBool>>,
+  waker: Arc<Mutex<Option<Waker>>>,
+}
+
+/// Error returned by [`ResultsQueue::push`].
+#[derive(Debug)]
+pub enum PushError {
+  /// The receiving end of the queue has disconnected; the entry was not
+  /// delivered.
+  Disconnected(DirEntryContents),
+  /// A stop signal was received on the `stop_flag`; the walk should unwind
+  /// without pushing further entries.
+  Stopped,
+}
+
+/// Non-blocking check of an optional stop signal. The flag is a permanent,
+/// multiply-observable state (as opposed to a one-shot channel message) so
+/// every clone of `ResultsQueue` and every consuming iterator sees it.
+fn is_stopped(stop_flag: Option<&AtomicBool>) -> bool {
+  match stop_flag {
+    Some(flag) => flag.load(AtomicOrdering::Relaxed),
+    None => false,
+  }
 }
 
 struct SortedResultsQueueNextMatcher {
@@ -24,35 +170,117 @@ struct SortedResultsQueueNextMatcher {
   remaining_siblings: Vec<usize>,
 }
 
-pub fn new_results_queue() -> (ResultsQueue, ResultsQueueIterator) {
-  let (sender, receiver) = mpsc::channel();
-  (ResultsQueue { sender }, ResultsQueueIterator { receiver })
+pub fn new_results_queue(
+  stop_flag: Option<Arc<AtomicBool>>,
+  progress_sender: Option<Sender<ScanProgress>>,
+) -> (ResultsQueue, ResultsQueueIterator) {
+  // Unbounded: a bounded channel could deadlock a blocked producer that is
+  // holding the exact entry a sorted consumer is waiting for.
+  let (sender, receiver) = crossbeam_channel::unbounded();
+  (
+    ResultsQueue {
+      sender,
+      stop_flag: stop_flag.clone(),
+      progress: progress_sender.map(ProgressState::new).map(Arc::new),
+      waker: None,
+    },
+    ResultsQueueIterator { receiver, stop_flag },
+  )
 }
 
-pub fn new_sorted_results_queue() -> (ResultsQueue, SortedResultsQueueIterator) {
-  let (sender, receiver) = mpsc::channel();
+pub fn new_sorted_results_queue(
+  stop_flag: Option<Arc<AtomicBool>>,
+  progress_sender: Option<Sender<ScanProgress>>,
+) -> (ResultsQueue, SortedResultsQueueIterator) {
+  let (sender, receiver) = crossbeam_channel::unbounded();
   (
-    ResultsQueue { sender },
+    ResultsQueue {
+      sender,
+      stop_flag: stop_flag.clone(),
+      progress: progress_sender.map(ProgressState::new).map(Arc::new),
+      waker: None,
+    },
     SortedResultsQueueIterator {
       receiver,
       next_matcher: SortedResultsQueueNextMatcher::default(),
       receive_buffer: BinaryHeap::new(),
+      stop_flag,
+    },
+  )
+}
+
+pub fn new_results_queue_stream(
+  stop_flag: Option<Arc<AtomicBool>>,
+  progress_sender: Option<Sender<ScanProgress>>,
+) -> (ResultsQueue, ResultsQueueStream) {
+  let (sender, receiver) = crossbeam_channel::unbounded();
+  let waker = Arc::new(Mutex::new(None));
+  (
+    ResultsQueue {
+      sender,
+      stop_flag: stop_flag.clone(),
+      progress: progress_sender.map(ProgressState::new).map(Arc::new),
+      waker: Some(waker.clone()),
+    },
+    ResultsQueueStream { receiver, stop_flag, waker },
+  )
+}
+
+pub fn new_sorted_results_queue_stream(
+  stop_flag: Option<Arc<AtomicBool>>,
+  progress_sender: Option<Sender<ScanProgress>>,
+) -> (ResultsQueue, SortedResultsQueueStream) {
+  let (sender, receiver) = crossbeam_channel::unbounded();
+  let waker = Arc::new(Mutex::new(None));
+  (
+    ResultsQueue {
+      sender,
+      stop_flag: stop_flag.clone(),
+      progress: progress_sender.map(ProgressState::new).map(Arc::new),
+      waker: Some(waker.clone()),
+    },
+    SortedResultsQueueStream {
+      receiver,
+      next_matcher: SortedResultsQueueNextMatcher::default(),
+      receive_buffer: BinaryHeap::new(),
+      stop_flag,
+      waker,
     },
   )
 }
 
 impl ResultsQueue {
-  pub fn push(
-    &self,
-    dent: DirEntryContents,
-  ) -> std::result::Result<(), SendError<DirEntryContents>> {
-    self.sender.send(dent)
+  pub fn push(&self, dent: DirEntryContents) -> std::result::Result<(), PushError> {
+    if is_stopped(self.stop_flag.as_deref()) {
+      return Err(PushError::Stopped);
+    }
+
+    if let Some(progress) = &self.progress {
+      progress.record(&dent);
+    }
+
+    let result = self
+      .sender
+      .send(dent)
+      .map_err(|SendError(dent)| PushError::Disconnected(dent));
+
+    if let Some(waker) = &self.waker {
+      if let Some(waker) = waker.lock().unwrap().take() {
+        waker.wake();
+      }
+    }
+
+    result
   }
 }
 
 impl Iterator for ResultsQueueIterator {
   type Item = DirEntryContents;
   fn next(&mut self) -> Option<DirEntryContents> {
+    if is_stopped(self.stop_flag.as_deref()) {
+      return None;
+    }
+
     match self.receiver.recv() {
       Ok(entry) => Some(entry),
       Err(_) => None,
@@ -60,31 +288,160 @@ impl Iterator for ResultsQueueIterator {
   }
 }
 
+/// Core of the "drain whatever's ready, then block once" strategy behind
+/// `SortedResultsQueueIterator::next`: pull in everything already queued
+/// without blocking and return it if `matches` is satisfied, otherwise park
+/// on a single blocking `recv` and try again. `is_stopped` and `is_done` are
+/// rechecked on every iteration, not just once up front, so a signal raised
+/// while this is parked waiting on a subtree that simply hasn't produced its
+/// next entry yet is noticed as soon as some `recv` call returns. Generic
+/// over `T` and the predicates so the multi-producer try_recv/recv
+/// interplay can be exercised with real threads and a real
+/// `crossbeam_channel` in tests (see `sorted_matcher_tests` below) without needing a
+/// `DirEntryContents`.
+fn next_matching<T: Ord>(
+  receiver: &Receiver<T>,
+  buffer: &mut BinaryHeap<T>,
+  matches: impl Fn(&T) -> bool,
+  is_done: impl Fn() -> bool,
+  is_stopped: impl Fn() -> bool,
+) -> Option<T> {
+  'outer: while !buffer.peek().map(&matches).unwrap_or(false) {
+    if is_stopped() {
+      buffer.clear();
+      return None;
+    }
+
+    if is_done() {
+      return None;
+    }
+
+    // Opportunistically pull in anything already queued without blocking,
+    // since it might turn out to be (or unblock) the entry we need.
+    loop {
+      match receiver.try_recv() {
+        Ok(item) => buffer.push(item),
+        Err(TryRecvError::Empty) => break,
+        Err(TryRecvError::Disconnected) => break 'outer,
+      }
+    }
+
+    if buffer.peek().map(&matches).unwrap_or(false) {
+      break;
+    }
+
+    // Nothing usable is queued yet. Park the thread instead of spinning;
+    // the producer will wake us as soon as it sends the next entry.
+    match receiver.recv() {
+      Ok(item) => buffer.push(item),
+      Err(_) => break,
+    }
+
+    if is_stopped() {
+      buffer.clear();
+      return None;
+    }
+  }
+
+  buffer.pop()
+}
+
 impl Iterator for SortedResultsQueueIterator {
   type Item = DirEntryContents;
   fn next(&mut self) -> Option<DirEntryContents> {
-    while self.receive_buffer.peek().map(|i| &i.index_path) != Some(&self.next_matcher.index_path) {
-      if self.next_matcher.is_none() {
-        return None;
+    let target = self.next_matcher.index_path.clone();
+    let item = next_matching(
+      &self.receiver,
+      &mut self.receive_buffer,
+      |entry: &DirEntryContents| entry.index_path == target,
+      || self.next_matcher.is_none(),
+      || is_stopped(self.stop_flag.as_deref()),
+    )?;
+    self.next_matcher.increment_past(&item);
+    Some(item)
+  }
+}
+
+impl Stream for ResultsQueueStream {
+  type Item = DirEntryContents;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<DirEntryContents>> {
+    let this = self.get_mut();
+
+    if is_stopped(this.stop_flag.as_deref()) {
+      return Poll::Ready(None);
+    }
+
+    match this.receiver.try_recv() {
+      Ok(entry) => Poll::Ready(Some(entry)),
+      Err(TryRecvError::Disconnected) => Poll::Ready(None),
+      Err(TryRecvError::Empty) => {
+        *this.waker.lock().unwrap() = Some(cx.waker().clone());
+        // Re-check in case the producer pushed between the try_recv above
+        // and registering the waker.
+        match this.receiver.try_recv() {
+          Ok(entry) => Poll::Ready(Some(entry)),
+          Err(TryRecvError::Disconnected) => Poll::Ready(None),
+          Err(TryRecvError::Empty) => Poll::Pending,
+        }
       }
+    }
+  }
+}
+
+impl Stream for SortedResultsQueueStream {
+  type Item = DirEntryContents;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<DirEntryContents>> {
+    let this = self.get_mut();
 
-      match self.receiver.try_recv() {
+    if is_stopped(this.stop_flag.as_deref()) {
+      this.receive_buffer.clear();
+      return Poll::Ready(None);
+    }
+
+    loop {
+      if this.receive_buffer.peek().map(|i| &i.index_path) == Some(&this.next_matcher.index_path) {
+        break;
+      }
+      if this.next_matcher.is_none() {
+        return Poll::Ready(None);
+      }
+      // Re-checked on every iteration so a stop signal set while this poll
+      // is still pulling in queued entries ends the stream at once. A stop
+      // set after this returns `Poll::Pending` is only seen on the next poll.
+      if is_stopped(this.stop_flag.as_deref()) {
+        this.receive_buffer.clear();
+        return Poll::Ready(None);
+      }
+
+      match this.receiver.try_recv() {
         Ok(dentry) => {
-          self.receive_buffer.push(dentry);
-          return self.receive_buffer.pop();
+          this.receive_buffer.push(dentry);
+          continue;
+        }
+        Err(TryRecvError::Disconnected) => break,
+        Err(TryRecvError::Empty) => {
+          *this.waker.lock().unwrap() = Some(cx.waker().clone());
+          // Re-check after registering to avoid missing a push that landed
+          // between the try_recv above and registering the waker.
+          match this.receiver.try_recv() {
+            Ok(dentry) => {
+              this.receive_buffer.push(dentry);
+              continue;
+            }
+            Err(TryRecvError::Disconnected) => break,
+            Err(TryRecvError::Empty) => return Poll::Pending,
+          }
         }
-        Err(err) => match err {
-          TryRecvError::Empty => thread::yield_now(),
-          TryRecvError::Disconnected => break,
-        },
       }
     }
 
-    if let Some(item) = self.receive_buffer.pop() {
-      self.next_matcher.increment_past(&item);
-      Some(item)
+    if let Some(item) = this.receive_buffer.pop() {
+      this.next_matcher.increment_past(&item);
+      Poll::Ready(Some(item))
     } else {
-      None
+      Poll::Ready(None)
     }
   }
 }
@@ -95,16 +452,22 @@ impl SortedResultsQueueNextMatcher {
   }
 
   fn increment_past(&mut self, entry: &DirEntryContents) {
+    self.advance(entry.remaining_folders_with_contents)
+  }
+
+  /// Core preorder bookkeeping behind `increment_past`, taking only the one
+  /// field it actually reads. Kept separate from `DirEntryContents` so the
+  /// traversal-order logic can be unit tested directly (see `sorted_matcher_tests` below)
+  /// without needing to construct one.
+  fn advance(&mut self, remaining_folders_with_contents: usize) {
     // Decrement remaining siblings at this level
     *self.remaining_siblings.last_mut().unwrap() -= 1;
 
-    if entry.remaining_folders_with_contents > 0 {
+    if remaining_folders_with_contents > 0 {
       // If visited item has children then push 0 index path, since we are now
       // looking for the first child.
       self.index_path.push(0);
-      self
-        .remaining_siblings
-        .push(entry.remaining_folders_with_contents);
+      self.remaining_siblings.push(remaining_folders_with_contents);
     } else {
       // Incrememnt sibling index
       *self.index_path.last_mut().unwrap() += 1;
@@ -130,3 +493,312 @@ impl Default for SortedResultsQueueNextMatcher {
     }
   }
 }
+
+#[cfg(test)]
+mod sorted_matcher_tests {
+  use super::*;
+
+  // Walks a matcher through a tree and returns the sequence of index_paths
+  // it expected to see, in the order it expected them — i.e. the preorder
+  // `SortedResultsQueueIterator` reconstructs from out-of-order pushes.
+  fn preorder_paths(remaining_folders_with_contents: &[usize]) -> Vec<Vec<usize>> {
+    let mut matcher = SortedResultsQueueNextMatcher::default();
+    let mut seen = Vec::new();
+    for &remaining in remaining_folders_with_contents {
+      assert!(!matcher.is_none(), "matcher finished before all entries were advanced past");
+      seen.push(matcher.index_path.clone());
+      matcher.advance(remaining);
+    }
+    seen
+  }
+
+  #[test]
+  fn flat_siblings_stay_in_order() {
+    // root/{a, b, c}, none of which have children of their own.
+    let paths = preorder_paths(&[3, 0, 0, 0]);
+    assert_eq!(paths, vec![vec![0], vec![0, 0], vec![0, 1], vec![0, 2]]);
+  }
+
+  #[test]
+  fn nested_folder_is_visited_before_its_following_sibling() {
+    // root/{dir/{leaf}, sibling}
+    let paths = preorder_paths(&[2, 1, 0, 0]);
+    assert_eq!(
+      paths,
+      vec![vec![0], vec![0, 0], vec![0, 0, 0], vec![0, 1]],
+      "blocking recv must not reorder a nested child ahead of/behind its parent's siblings"
+    );
+  }
+
+  // Stands in for `DirEntryContents` (defined in the not-yet-present `walk`
+  // module) so `next_matching` — the exact try_recv/recv loop behind
+  // `SortedResultsQueueIterator::next` — can be driven against a real
+  // `crossbeam_channel` fed by real threads, instead of only the pure
+  // bookkeeping above.
+  #[derive(Clone, Eq, PartialEq)]
+  struct TestEntry {
+    index_path: Vec<usize>,
+    remaining_folders_with_contents: usize,
+  }
+
+  // Reversed so the `BinaryHeap` keeps the smallest `index_path` on top,
+  // which is what `next_matching`'s `peek`/`pop` rely on.
+  impl Ord for TestEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+      self.index_path.cmp(&other.index_path).reverse()
+    }
+  }
+
+  impl PartialOrd for TestEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+      Some(self.cmp(other))
+    }
+  }
+
+  #[test]
+  fn reassembles_preorder_from_out_of_order_pushes_across_threads() {
+    // root/{dir/{leaf}, sibling}, same shape as the test above.
+    let entries = [
+      TestEntry { index_path: vec![0], remaining_folders_with_contents: 2 },
+      TestEntry { index_path: vec![0, 0], remaining_folders_with_contents: 1 },
+      TestEntry { index_path: vec![0, 0, 0], remaining_folders_with_contents: 0 },
+      TestEntry { index_path: vec![0, 1], remaining_folders_with_contents: 0 },
+    ];
+
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    // Push out of their preorder, staggered across threads, so the consumer
+    // below is forced through both the non-blocking drain and the blocking
+    // `recv` path chunk0-1 introduced, rather than finding everything
+    // already queued.
+    let push_order = [2, 0, 3, 1];
+    let mut producers = Vec::new();
+    for (delay, &i) in push_order.iter().enumerate() {
+      let sender = sender.clone();
+      let entry = entries[i].clone();
+      producers.push(std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(5 * delay as u64));
+        sender.send(entry).unwrap();
+      }));
+    }
+    drop(sender);
+
+    let mut matcher = SortedResultsQueueNextMatcher::default();
+    let mut buffer = BinaryHeap::new();
+    let mut reassembled = Vec::new();
+    while !matcher.is_none() {
+      let target = matcher.index_path.clone();
+      let next = next_matching(
+        &receiver,
+        &mut buffer,
+        |entry: &TestEntry| entry.index_path == target,
+        || false,
+        || false,
+      );
+      match next {
+        Some(entry) => {
+          reassembled.push(entry.index_path.clone());
+          matcher.advance(entry.remaining_folders_with_contents);
+        }
+        None => break,
+      }
+    }
+    for producer in producers {
+      producer.join().unwrap();
+    }
+
+    assert_eq!(
+      reassembled,
+      vec![vec![0], vec![0, 0], vec![0, 0, 0], vec![0, 1]],
+      "preorder must be reconstructed regardless of arrival order across producer threads"
+    );
+  }
+}
+
+/// A caller-supplied ordering over `DirEntry`s, e.g.
+/// `|a, b| a.len().cmp(&b.len())` for "largest first".
+type Comparator = Arc<dyn Fn(&DirEntry, &DirEntry) -> Ordering + Send + Sync>;
+
+/// Entry ordered by a caller-supplied comparator rather than by traversal
+/// `index_path`. `Ordering::Greater` means "sorts first" in the final
+/// output, matching the intuitive reading of a comparator like
+/// `|a, b| a.len().cmp(&b.len())` for "largest first".
+struct OrderedEntry {
+  dent: DirEntryContents,
+  comparator: Comparator,
+}
+
+impl Ord for OrderedEntry {
+  fn cmp(&self, other: &Self) -> Ordering {
+    (self.comparator)(self.dent.dir_entry(), other.dent.dir_entry())
+  }
+}
+
+impl PartialOrd for OrderedEntry {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl PartialEq for OrderedEntry {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for OrderedEntry {}
+
+/// Pushes `item` onto a min-heap of kept entries, evicting the current
+/// minimum once `limit` is exceeded. Kept separate from `DirEntryContents`
+/// so the eviction direction can be unit tested directly (see
+/// `ordered_queue_tests` below) against plain `Ord` types.
+fn push_bounded<T: Ord>(heap: &mut BinaryHeap<Reverse<T>>, item: T, limit: Option<usize>) {
+  heap.push(Reverse(item));
+  if let Some(limit) = limit {
+    if heap.len() > limit {
+      heap.pop();
+    }
+  }
+}
+
+/// Drains a min-heap built by `push_bounded`, returning its contents
+/// best-first (i.e. reversing the ascending pop order).
+fn drain_best<T: Ord>(heap: &mut BinaryHeap<Reverse<T>>) -> Vec<T> {
+  let mut out = Vec::with_capacity(heap.len());
+  while let Some(Reverse(item)) = heap.pop() {
+    out.push(item);
+  }
+  out.reverse();
+  out
+}
+
+/// Iterator produced by [`new_ordered_results_queue`] /
+/// [`new_top_n_results_queue`]. Unlike [`SortedResultsQueueIterator`], this
+/// cannot stream: the caller's ordering can only be known once every entry
+/// has arrived, so nothing is yielded until the producer disconnects, at
+/// which point the buffered entries drain in order. If a stop signal is seen
+/// first, the buffered entries are discarded and nothing is yielded.
+pub struct OrderedResultsQueueIterator {
+  receiver: Receiver<DirEntryContents>,
+  stop_flag: Option<Arc<AtomicBool>>,
+  comparator: Comparator,
+  // Keeps only the `limit` best entries (per `comparator`) while buffering,
+  // to bound memory for queries like "largest 100 files". `None` buffers
+  // everything.
+  limit: Option<usize>,
+  heap: BinaryHeap<Reverse<OrderedEntry>>,
+  drained: Option<std::vec::IntoIter<DirEntryContents>>,
+}
+
+/// Orders the *entire* flattened walk output by `cmp`, instead of the
+/// depth-first preorder `SortedResultsQueueIterator` reconstructs. Because
+/// the full ordering isn't known until the walk finishes, this buffers
+/// every entry in memory and only starts yielding once the producer
+/// disconnects — it cannot stream results incrementally like the other
+/// queues in this module.
+pub fn new_ordered_results_queue(
+  stop_flag: Option<Arc<AtomicBool>>,
+  progress_sender: Option<Sender<ScanProgress>>,
+  cmp: impl Fn(&DirEntry, &DirEntry) -> Ordering + Send + Sync + 'static,
+) -> (ResultsQueue, OrderedResultsQueueIterator) {
+  new_ordered_results_queue_with_limit(stop_flag, progress_sender, cmp, None)
+}
+
+/// Like [`new_ordered_results_queue`], but only ever keeps the `n` best
+/// entries (per `cmp`) buffered, bounding memory for queries such as
+/// "largest 100 files" where the full result set would otherwise need to
+/// be held.
+pub fn new_top_n_results_queue(
+  stop_flag: Option<Arc<AtomicBool>>,
+  progress_sender: Option<Sender<ScanProgress>>,
+  cmp: impl Fn(&DirEntry, &DirEntry) -> Ordering + Send + Sync + 'static,
+  n: usize,
+) -> (ResultsQueue, OrderedResultsQueueIterator) {
+  new_ordered_results_queue_with_limit(stop_flag, progress_sender, cmp, Some(n))
+}
+
+fn new_ordered_results_queue_with_limit(
+  stop_flag: Option<Arc<AtomicBool>>,
+  progress_sender: Option<Sender<ScanProgress>>,
+  cmp: impl Fn(&DirEntry, &DirEntry) -> Ordering + Send + Sync + 'static,
+  limit: Option<usize>,
+) -> (ResultsQueue, OrderedResultsQueueIterator) {
+  let (sender, receiver) = crossbeam_channel::unbounded();
+  (
+    ResultsQueue {
+      sender,
+      stop_flag: stop_flag.clone(),
+      progress: progress_sender.map(ProgressState::new).map(Arc::new),
+      waker: None,
+    },
+    OrderedResultsQueueIterator {
+      receiver,
+      stop_flag,
+      comparator: Arc::new(cmp),
+      limit,
+      heap: BinaryHeap::new(),
+      drained: None,
+    },
+  )
+}
+
+impl Iterator for OrderedResultsQueueIterator {
+  type Item = DirEntryContents;
+
+  fn next(&mut self) -> Option<DirEntryContents> {
+    if let Some(drained) = &mut self.drained {
+      return drained.next();
+    }
+
+    loop {
+      if is_stopped(self.stop_flag.as_deref()) {
+        self.heap.clear();
+        break;
+      }
+
+      match self.receiver.recv() {
+        Ok(dent) => {
+          let entry = OrderedEntry {
+            dent,
+            comparator: self.comparator.clone(),
+          };
+          push_bounded(&mut self.heap, entry, self.limit);
+        }
+        Err(_) => break,
+      }
+    }
+
+    let ordered: Vec<DirEntryContents> = drain_best(&mut self.heap)
+      .into_iter()
+      .map(|entry| entry.dent)
+      .collect();
+    let mut drained = ordered.into_iter();
+    let next = drained.next();
+    self.drained = Some(drained);
+    next
+  }
+}
+
+#[cfg(test)]
+mod ordered_queue_tests {
+  use super::*;
+
+  #[test]
+  fn unbounded_keeps_all_entries_descending() {
+    let mut heap: BinaryHeap<Reverse<i32>> = BinaryHeap::new();
+    for n in [3, 1, 4, 1, 5, 9, 2, 6] {
+      push_bounded(&mut heap, n, None);
+    }
+    assert_eq!(drain_best(&mut heap), vec![9, 6, 5, 4, 3, 2, 1, 1]);
+  }
+
+  #[test]
+  fn top_n_keeps_the_largest_entries_descending() {
+    let mut heap: BinaryHeap<Reverse<i32>> = BinaryHeap::new();
+    for n in [5, 1, 9, 2, 8, 3, 7, 4, 6, 0] {
+      push_bounded(&mut heap, n, Some(3));
+    }
+    // Must be the 3 largest values (9, 8, 7), best-first — not the 3
+    // smallest and not an unordered set.
+    assert_eq!(drain_best(&mut heap), vec![9, 8, 7]);
+  }
+}